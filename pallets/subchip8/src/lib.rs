@@ -35,6 +35,9 @@ const FONTSET: [u8; 80] = [
 /// starting address for programs
 const START_ADDR: u16 = 0x200;
 
+/// Current version of the [`EmulatorSnapshot`] wire format.
+const SNAPSHOT_VERSION: u8 = 1;
+
 #[frame_support::pallet(dev_mode)]
 pub mod pallet {
     use super::*;
@@ -57,7 +60,7 @@ pub mod pallet {
         /// stack pointer
         pub sp: u16,
         /// stack for subroutine calls
-        pub stack: [u8; 16],
+        pub stack: [u16; 16],
         /// Keyboard state as a 16-bit bitfield.
         pub keys: u16,
         /// Delay timer
@@ -66,26 +69,70 @@ pub mod pallet {
         pub st: u8,
         /// Size of the loaded program
         pub program_size: primitive_types::U256,
+        /// Monotonic count of executed `CXNN` instructions, used to diversify
+        /// the per-instruction randomness seed within a single block.
+        pub instruction_counter: u64,
+        /// Ring buffer of the most recent program counter values, oldest
+        /// overwritten first; handy for diagnosing infinite loops and runaway
+        /// jumps from a client.
+        pub pc_history: [u16; 512],
+        /// Write index into `pc_history`.
+        pub pc_history_head: u16,
     }
 
     impl Default for Emulator {
         fn default() -> Self {
             Emulator {
+                pc: 0,
                 ram: [0; 4096],
                 display: [0; 256],
                 virtual_registers: [0; 16],
+                i_register: 0,
+                sp: 0,
                 stack: [0; 16],
-                ..Default::default()
+                keys: 0,
+                dt: 0,
+                st: 0,
+                program_size: primitive_types::U256::zero(),
+                instruction_counter: 0,
+                pc_history: [0; 512],
+                pc_history_head: 0,
             }
         }
     }
 
+    /// A versioned, SCALE-encodable capture of the full [`Emulator`] state, the
+    /// on-chain analogue of a cartridge emulator's save file. The `version`
+    /// header lets `import_state` reject snapshots from an incompatible format.
+    #[derive(Clone, Debug, Encode, Decode, TypeInfo, MaxEncodedLen)]
+    pub struct EmulatorSnapshot {
+        /// Wire-format version, see [`SNAPSHOT_VERSION`].
+        pub version: u8,
+        /// The complete machine state at the time of export.
+        pub emulator: Emulator,
+    }
+
     #[pallet::storage]
     pub type emulator<T> = StorageValue<_, Emulator, ValueQuery>;
+
+    /// Addresses the run loop should break on before fetching an instruction.
+    #[pallet::storage]
+    pub type Breakpoints<T> = StorageValue<_, BoundedVec<u16, ConstU32<64>>, ValueQuery>;
+
+    /// Set once a breakpoint is hit or an instruction faults; cleared by
+    /// `continue_run`/`step`.
+    #[pallet::storage]
+    pub type Halted<T> = StorageValue<_, bool, ValueQuery>;
     #[pallet::config(with_default)]
     pub trait Config: frame_system::Config {
         #[pallet::no_default_bounds]
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Deterministic randomness source for the `CXNN` opcode. A runtime must
+        /// produce identical output on every validator, so we draw from the
+        /// chain's own randomness beacon rather than an off-chain RNG.
+        #[pallet::no_default]
+        type Randomness: frame_support::traits::Randomness<Self::Hash, BlockNumberFor<Self>>;
     }
 
     /// genesis state, load tye fontset in the emulator RAM
@@ -110,8 +157,8 @@ pub mod pallet {
     // ---------------------------* hooks function *--------------------------- //
     #[pallet::hooks]
     impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
-        fn on_finalize(_n:BlockNumber){
-            Self::execute();
+        fn on_finalize(_n: BlockNumberFor<T>){
+            Self::tick();
         }
     }
 
@@ -121,59 +168,91 @@ pub mod pallet {
     impl<T: Config> Pallet<T>{
 
         /// pop value from stack
-        pub fn pop() -> u16 {
-            let emu = emulator::<T>::get();
-            ensure!(emu.sp < 1, Error::<T>::StackUndeflow);
-            emulator::<T>::mutate(|emu|{
-                emu.sp -= 1;
-            });
-            emu.stack[emu.sp]
+        pub fn pop(emu: &mut Emulator) -> Result<u16, Error<T>> {
+            ensure!(emu.sp > 0, Error::<T>::StackUndeflow);
+            emu.sp -= 1;
+            Ok(emu.stack[emu.sp as usize])
         }
 
-        /// push value from stack
-        pub fn push(val:u16){
-            let emu = emulator::<T>::get();
+        /// push value onto stack
+        pub fn push(emu: &mut Emulator, val: u16) -> Result<(), Error<T>> {
             // it should be less that stack size (16)
             ensure!(emu.sp < 16, Error::<T>::StackOverflow);
-            emulator::<T>::mutate(|emu|{
-                emu.stack.push(val);
-                emu.sp += 1;
-            });
+            emu.stack[emu.sp as usize] = val;
+            emu.sp += 1;
+            Ok(())
         }
 
         /// CPU processing loop
         /// This function is called once per tick of the CPU.
         /// Fetch the next instruction, decode and execute it.
         pub fn tick(){
-            // Fetch
-            let op_code = Self::fetch();
-            // Decode & execute
-            Self::execute(op_code);
+            // Halt before fetching if the upcoming address carries a breakpoint.
+            let pc = emulator::<T>::get().pc;
+            if Breakpoints::<T>::get().contains(&pc) {
+                Halted::<T>::set(true);
+                Self::deposit_event(Event::BreakpointHit { pc });
+                return;
+            }
+            // Fetch; a fetch past the end of RAM halts the run rather than panicking.
+            let op_code = match Self::fetch() {
+                Ok(op) => op,
+                Err(_) => {
+                    Halted::<T>::set(true);
+                    Self::deposit_event(Event::ExecutionFault { pc });
+                    return;
+                }
+            };
+            // Decode & execute; any execution error (unknown opcode, out-of-bounds
+            // memory access, stack overflow/underflow) halts the run and is
+            // surfaced to clients instead of being silently swallowed.
+            if Self::execute(op_code).is_err() {
+                Halted::<T>::set(true);
+                Self::deposit_event(Event::ExecutionFault { pc });
+                return;
+            }
+            // Record the retired program counter into the history ring buffer.
+            emulator::<T>::mutate(|emu|{
+                let head = emu.pc_history_head as usize;
+                emu.pc_history[head] = pc;
+                emu.pc_history_head = ((head + 1) % 512) as u16;
+            });
 
             Self::tick_timers();
         }
 
         pub fn tick_timers(){
-            let emu = emulator::<T>::get();
-            if emu.dt > 0 {
-                emu.dt -= 1;
-            }
+            // Remember the sound timer before we decrement it so the beep event
+            // can report the value it fired on; the beep only fires on the 1 -> 0
+            // transition, so that value is always 1.
+            let duration = emulator::<T>::get().st;
+            let mut beeped = false;
+            emulator::<T>::mutate(|emu|{
+                if emu.dt > 0 {
+                    emu.dt -= 1;
+                }
 
-            if emu.st > 0 {
-                if emu.st == 1 {
-                    // BEEP
+                if emu.st > 0 {
+                    if emu.st == 1 {
+                        // The sound timer is about to reach zero: surface the beep.
+                        beeped = true;
+                    }
+                    emu.st -= 1;
                 }
-                emu.st -= 1;
+            });
+
+            if beeped {
+                Self::deposit_event(Event::Beep { duration });
             }
         }
 
         /// fetch the next instruction
-        pub fn fetch() -> u16 {
-            let emu = emulator::<T>::get();
+        pub fn fetch() -> Result<u16, Error<T>> {
+            let mut emu = emulator::<T>::get();
             // if its less than RAM SiZE
             ensure!(emu.pc + 1 < 4096, Error::<T>::MemoryOutOfBounds);
-            let higher_byte = emu.ram[emu.pc];
-            let lower_byte = emu.ram[emu.pc + 1];
+            let higher_byte = emu.ram[emu.pc as usize];
+            let lower_byte = emu.ram[emu.pc as usize + 1];
             // form the full opcode
             // example
             // higher_byte = 0xA2 = 1010 0010
@@ -182,14 +261,244 @@ pub mod pallet {
             // lower_byte  = 0xF0 = 0000 0000 1111 0000
             //
             // Result      = 1010 0010 1111 0000 (0xA2F0)
-            let op_code = higher_byte << 8 | lower_byte;
+            let op_code = (higher_byte as u16) << 8 | lower_byte as u16;
             emu.pc += 2;
             emulator::<T>::set(emu);
-            op_code
+            Ok(op_code)
         }
 
-        pub fn execute(op:u16){
-            todo!()
+        /// Decode a single 16-bit opcode into its nibbles and execute it against
+        /// the [`Emulator`] storage. Returns an error on an opcode we do not know
+        /// about rather than panicking, so a malformed ROM cannot take the runtime
+        /// down.
+        pub fn execute(op: u16) -> DispatchResult {
+            let digit1 = (op & 0xF000) >> 12;
+            let x = ((op & 0x0F00) >> 8) as usize;
+            let y = ((op & 0x00F0) >> 4) as usize;
+            let n = (op & 0x000F) as u8;
+            let nn = (op & 0x00FF) as u8;
+            let nnn = op & 0x0FFF;
+
+            let mut emu = emulator::<T>::get();
+
+            match (digit1, x as u16, y as u16, n as u16) {
+                // 0000 - no-op
+                (0, 0, 0, 0) => {}
+                // 00E0 - clear display
+                (0, 0, 0xE, 0) => {
+                    emu.display = [0; 256];
+                }
+                // 00EE - return from subroutine
+                (0, 0, 0xE, 0xE) => {
+                    emu.pc = Self::pop(&mut emu)?;
+                }
+                // 1NNN - jump
+                (1, _, _, _) => {
+                    emu.pc = nnn;
+                }
+                // 2NNN - call subroutine
+                (2, _, _, _) => {
+                    let pc = emu.pc;
+                    Self::push(&mut emu, pc)?;
+                    emu.pc = nnn;
+                }
+                // 3XNN - skip if Vx == NN
+                (3, _, _, _) => {
+                    if emu.virtual_registers[x] == nn {
+                        emu.pc += 2;
+                    }
+                }
+                // 4XNN - skip if Vx != NN
+                (4, _, _, _) => {
+                    if emu.virtual_registers[x] != nn {
+                        emu.pc += 2;
+                    }
+                }
+                // 5XY0 - skip if Vx == Vy
+                (5, _, _, 0) => {
+                    if emu.virtual_registers[x] == emu.virtual_registers[y] {
+                        emu.pc += 2;
+                    }
+                }
+                // 6XNN - set Vx = NN
+                (6, _, _, _) => {
+                    emu.virtual_registers[x] = nn;
+                }
+                // 7XNN - add NN to Vx (no carry)
+                (7, _, _, _) => {
+                    emu.virtual_registers[x] = emu.virtual_registers[x].wrapping_add(nn);
+                }
+                // 8XY0 - set Vx = Vy
+                (8, _, _, 0) => {
+                    emu.virtual_registers[x] = emu.virtual_registers[y];
+                }
+                // 8XY1 - Vx |= Vy
+                (8, _, _, 1) => {
+                    emu.virtual_registers[x] |= emu.virtual_registers[y];
+                }
+                // 8XY2 - Vx &= Vy
+                (8, _, _, 2) => {
+                    emu.virtual_registers[x] &= emu.virtual_registers[y];
+                }
+                // 8XY3 - Vx ^= Vy
+                (8, _, _, 3) => {
+                    emu.virtual_registers[x] ^= emu.virtual_registers[y];
+                }
+                // 8XY4 - Vx += Vy, VF = carry
+                (8, _, _, 4) => {
+                    let (res, carry) =
+                        emu.virtual_registers[x].overflowing_add(emu.virtual_registers[y]);
+                    emu.virtual_registers[x] = res;
+                    emu.virtual_registers[0xF] = carry as u8;
+                }
+                // 8XY5 - Vx -= Vy, VF = !borrow
+                (8, _, _, 5) => {
+                    let (res, borrow) =
+                        emu.virtual_registers[x].overflowing_sub(emu.virtual_registers[y]);
+                    emu.virtual_registers[x] = res;
+                    emu.virtual_registers[0xF] = !borrow as u8;
+                }
+                // 8XY6 - Vx >>= 1, VF = dropped bit
+                (8, _, _, 6) => {
+                    let lsb = emu.virtual_registers[x] & 1;
+                    emu.virtual_registers[x] >>= 1;
+                    emu.virtual_registers[0xF] = lsb;
+                }
+                // 8XY7 - Vx = Vy - Vx, VF = !borrow
+                (8, _, _, 7) => {
+                    let (res, borrow) =
+                        emu.virtual_registers[y].overflowing_sub(emu.virtual_registers[x]);
+                    emu.virtual_registers[x] = res;
+                    emu.virtual_registers[0xF] = !borrow as u8;
+                }
+                // 8XYE - Vx <<= 1, VF = dropped bit
+                (8, _, _, 0xE) => {
+                    let msb = (emu.virtual_registers[x] >> 7) & 1;
+                    emu.virtual_registers[x] <<= 1;
+                    emu.virtual_registers[0xF] = msb;
+                }
+                // 9XY0 - skip if Vx != Vy
+                (9, _, _, 0) => {
+                    if emu.virtual_registers[x] != emu.virtual_registers[y] {
+                        emu.pc += 2;
+                    }
+                }
+                // ANNN - set I
+                (0xA, _, _, _) => {
+                    emu.i_register = nnn;
+                }
+                // BNNN - jump to NNN + V0
+                (0xB, _, _, _) => {
+                    emu.pc = nnn + emu.virtual_registers[0] as u16;
+                }
+                // CXNN - random AND NN, drawn from the deterministic on-chain source
+                (0xC, _, _, _) => {
+                    let (seed, _) = T::Randomness::random(b"chip8_cxnn");
+                    // Fold the beacon output together with the current pc and the
+                    // instruction counter so repeated CXNN within one block differ
+                    // while staying reproducible on re-execution.
+                    let hash = (seed, emu.pc, emu.instruction_counter)
+                        .using_encoded(sp_io::hashing::blake2_256);
+                    emu.instruction_counter = emu.instruction_counter.wrapping_add(1);
+                    emu.virtual_registers[x] = hash[0] & nn;
+                }
+                // DXYN - draw N-byte sprite at (Vx, Vy), VF = collision
+                (0xD, _, _, _) => {
+                    let vx = emu.virtual_registers[x] as usize;
+                    let vy = emu.virtual_registers[y] as usize;
+                    let mut flipped = false;
+                    for row in 0..n as usize {
+                        let addr = emu.i_register as usize + row;
+                        ensure!(addr < 4096, Error::<T>::MemoryOutOfBounds);
+                        let sprite = emu.ram[addr];
+                        for col in 0..8usize {
+                            if (sprite & (0x80 >> col)) != 0 {
+                                let px = (vx + col) % 64;
+                                let py = (vy + row) % 32;
+                                let byte_idx = py * 8 + px / 8;
+                                let bit = 7 - (px % 8);
+                                if emu.display[byte_idx] & (1 << bit) != 0 {
+                                    flipped = true;
+                                }
+                                emu.display[byte_idx] ^= 1 << bit;
+                            }
+                        }
+                    }
+                    emu.virtual_registers[0xF] = flipped as u8;
+                }
+                // EX9E - skip if key Vx is pressed
+                (0xE, _, 9, 0xE) => {
+                    let key = emu.virtual_registers[x] as u16;
+                    if emu.keys & (1 << key) != 0 {
+                        emu.pc += 2;
+                    }
+                }
+                // EXA1 - skip if key Vx is not pressed
+                (0xE, _, 0xA, 1) => {
+                    let key = emu.virtual_registers[x] as u16;
+                    if emu.keys & (1 << key) == 0 {
+                        emu.pc += 2;
+                    }
+                }
+                // FX07 - Vx = delay timer
+                (0xF, _, 0, 7) => {
+                    emu.virtual_registers[x] = emu.dt;
+                }
+                // FX0A - wait for a key press, store in Vx
+                (0xF, _, 0, 0xA) => {
+                    if emu.keys == 0 {
+                        // no key down: re-run this instruction on the next tick
+                        emu.pc -= 2;
+                    } else {
+                        emu.virtual_registers[x] = emu.keys.trailing_zeros() as u8;
+                    }
+                }
+                // FX15 - delay timer = Vx
+                (0xF, _, 1, 5) => {
+                    emu.dt = emu.virtual_registers[x];
+                }
+                // FX18 - sound timer = Vx
+                (0xF, _, 1, 8) => {
+                    emu.st = emu.virtual_registers[x];
+                }
+                // FX1E - I += Vx
+                (0xF, _, 1, 0xE) => {
+                    emu.i_register = emu.i_register.wrapping_add(emu.virtual_registers[x] as u16);
+                }
+                // FX29 - I = font sprite address for digit in Vx
+                (0xF, _, 2, 9) => {
+                    emu.i_register = (emu.virtual_registers[x] as u16) * 5;
+                }
+                // FX33 - BCD of Vx into ram[I..I+3]
+                (0xF, _, 3, 3) => {
+                    let val = emu.virtual_registers[x];
+                    let i = emu.i_register as usize;
+                    ensure!(i + 2 < 4096, Error::<T>::MemoryOutOfBounds);
+                    emu.ram[i] = val / 100;
+                    emu.ram[i + 1] = (val / 10) % 10;
+                    emu.ram[i + 2] = val % 10;
+                }
+                // FX55 - store V0..=Vx into ram[I..]
+                (0xF, _, 5, 5) => {
+                    let i = emu.i_register as usize;
+                    ensure!(i + x < 4096, Error::<T>::MemoryOutOfBounds);
+                    for idx in 0..=x {
+                        emu.ram[i + idx] = emu.virtual_registers[idx];
+                    }
+                }
+                // FX65 - load V0..=Vx from ram[I..]
+                (0xF, _, 6, 5) => {
+                    let i = emu.i_register as usize;
+                    ensure!(i + x < 4096, Error::<T>::MemoryOutOfBounds);
+                    for idx in 0..=x {
+                        emu.virtual_registers[idx] = emu.ram[i + idx];
+                    }
+                }
+                _ => return Err(Error::<T>::UnknownOpcode.into()),
+            }
+
+            emulator::<T>::set(emu);
+            Ok(())
         }
     }
 
@@ -211,26 +520,104 @@ pub mod pallet {
         pub fn run(origin:OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
 
-            ensure!(emu.program_size < 1.into(), Error::<T>::ProgramSizeZero);
+            ensure!(emu.program_size > U256::zero(), Error::<T>::ProgramSizeZero);
 
             let end = emu.program_size.as_u128();
-            for instr in 0..end {
-                ensure!(emu.pc > 4096,Error::<T>::MemoryOutOfBounds);
-                let op_code = Self::fetch();
-                Self::execute(op_code);
+            for _ in 0..end {
+                ensure!(emu.pc < 4096, Error::<T>::MemoryOutOfBounds);
+                let op_code = Self::fetch()?;
+                Self::execute(op_code)?;
             }
             Ok(())
         }
         pub fn load(origin: OriginFor<T>, data: Vec<u8>) -> DispatchResult {
-            let start = START_ADDR;
+            let start = START_ADDR as usize;
             let end = start + data.len();
-            ensure!(end < 4096, Error::<T>::ProgramSizeTooLarge);
+            ensure!(end < 4096, Error::<T>::PrograSizeToolarge);
             emulator::<T>::mutate(|emu|{
                 emu.program_size = U256::from(data.len());
-                emu.ram.as_slice.copy_from_slice(data.as_slice());
+                emu.ram[start..end].copy_from_slice(&data);
+            });
+            Ok(())
+        }
+        // ---------------------------* snapshot functions *--------------------------- //
+        /// Serialize the current machine into a versioned snapshot and surface it
+        /// to clients so a paused emulator can be backed up.
+        pub fn export_state(origin: OriginFor<T>) -> DispatchResult {
+            let snapshot = EmulatorSnapshot {
+                version: SNAPSHOT_VERSION,
+                emulator: emulator::<T>::get(),
+            };
+            Self::deposit_event(Event::ReturnValue{
+                name: "snapshot",
+                value: snapshot.encode()
+            });
+            Ok(())
+        }
+
+        /// Restore a previously exported snapshot, rejecting any whose version is
+        /// unknown or whose `pc`/`program_size` fall outside RAM rather than
+        /// writing corrupt state.
+        pub fn import_state(origin: OriginFor<T>, snapshot: EmulatorSnapshot) -> DispatchResult {
+            ensure!(snapshot.version == SNAPSHOT_VERSION, Error::<T>::UnsupportedSnapshotVersion);
+            ensure!(
+                snapshot.emulator.pc < 4096
+                    && snapshot.emulator.program_size <= U256::from(4096u32),
+                Error::<T>::SnapshotOutOfBounds
+            );
+            emulator::<T>::set(snapshot.emulator);
+            Ok(())
+        }
+
+        // ---------------------------* debugger functions *--------------------------- //
+        /// Register an address the run loop should break on.
+        pub fn add_breakpoint(origin: OriginFor<T>, addr: u16) -> DispatchResult {
+            Breakpoints::<T>::try_mutate(|bps|{
+                if !bps.contains(&addr) {
+                    bps.try_push(addr).map_err(|_| Error::<T>::TooManyBreakpoints)?;
+                }
+                Ok(())
+            })
+        }
+
+        /// Remove a previously registered breakpoint.
+        pub fn remove_breakpoint(origin: OriginFor<T>, addr: u16) -> DispatchResult {
+            Breakpoints::<T>::mutate(|bps| bps.retain(|a| *a != addr));
+            Ok(())
+        }
+
+        /// Clear `Halted` and run exactly one `tick`. If the program counter sits
+        /// on a breakpoint, the tick halts again without executing anything.
+        pub fn step(origin: OriginFor<T>) -> DispatchResult {
+            Halted::<T>::set(false);
+            Self::tick();
+            Ok(())
+        }
+
+        /// Resume execution for up to `program_size` ticks, stopping early once a
+        /// breakpoint or an execution fault halts the loop.
+        pub fn continue_run(origin: OriginFor<T>) -> DispatchResult {
+            Halted::<T>::set(false);
+            let end = emulator::<T>::get().program_size.as_u128();
+            for _ in 0..end {
+                if Halted::<T>::get() {
+                    break;
+                }
+                Self::tick();
+            }
+            Ok(())
+        }
+
+        /// Deposit the recorded program counter trace for client-side diagnosis.
+        pub fn get_pc_history(origin: OriginFor<T>) -> DispatchResult {
+            let emu = emulator::<T>::get();
+            Self::deposit_event(Event::ReturnValue{
+                name: "pc_history",
+                value: emu.pc_history.encode()
             });
             Ok(())
         }
+
         // ---------------------------* keyboard functions *--------------------------- //
         /// Handle keypress event
         /// Index of the key (0-15)
@@ -239,7 +626,7 @@ pub mod pallet {
             emulator::<T>::mutate(|emu|{
                 if pressed {
                     // Set bit to 1 for pressed key
-                    emu.keys |= 1u16 << idx;
+                    emu.keys |= 1u16 << index;
 
                     // Example:
                     // index = 4
@@ -247,7 +634,7 @@ pub mod pallet {
                     // keys |= makes that bit 1
                 } else {
                     // Clear bit to 0 for released key
-                    emu.keys &= !(1u16 << idx);
+                    emu.keys &= !(1u16 << index);
 
                     // Example:
                     // index = 4
@@ -262,26 +649,26 @@ pub mod pallet {
         pub fn get_get_display(origin: OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
 
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("screen")),
-                value: Box::new(emu.display)
+            Self::deposit_event(Event::ReturnValue{
+                name: "screen",
+                value: emu.display.encode()
             });
             Ok(())
         }
         pub fn get_program_counter(origin: OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("program_counter")),
-                value: Box::new(emu.pc)
+            Self::deposit_event(Event::ReturnValue{
+                name: "program_counter",
+                value: emu.pc.encode()
             });
             Ok(())
         }
 
         pub fn get_keyboard_keys(origin: OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("keyboard_keys")),
-                value: Box::new(emu.keys)
+            Self::deposit_event(Event::ReturnValue{
+                name: "keyboard_keys",
+                value: emu.keys.encode()
             });
             Ok(())
         }
@@ -289,9 +676,9 @@ pub mod pallet {
         pub fn get_ram_value_at(origin: OriginFor<T>,index: u8) -> DispatchResult {
             let emu = emulator::<T>::get();
 
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("ram value")),
-                value: Box::new(emu.ram[index])
+            Self::deposit_event(Event::ReturnValue{
+                name: "ram value",
+                value: emu.ram[index as usize].encode()
             });
             Ok(())
         }
@@ -299,61 +686,61 @@ pub mod pallet {
         pub fn get_vregister(origin: OriginFor<T>,index: u8) -> DispatchResult {
             let emu = emulator::<T>::get();
 
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("ram value")),
-                value: Box::new(emu.virtual_registers[index])
+            Self::deposit_event(Event::ReturnValue{
+                name: "vregister",
+                value: emu.virtual_registers[index as usize].encode()
             });
             Ok(())
         }
 
         pub fn get_iregister(origin:OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("i_register")),
-                value: Box::new(emu.i_register)
+            Self::deposit_event(Event::ReturnValue{
+                name: "i_register",
+                value: emu.i_register.encode()
             });
             Ok(())
         }
 
         pub fn get_delay_timer(origin:OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("delay timer")),
-                value: Box::new(emu.dt)
+            Self::deposit_event(Event::ReturnValue{
+                name: "delay timer",
+                value: emu.dt.encode()
             });
             Ok(())
         }
 
         pub fn get_sound_timer(origin:OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("sound timer")),
-                value: Box::new(emu.st)
+            Self::deposit_event(Event::ReturnValue{
+                name: "sound timer",
+                value: emu.st.encode()
             });
             Ok(())
         }
 
         pub fn get_stack_pointer(origin:OriginFor<T>) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("stack pointer")),
-                value: Box::new(emu.sp)
+            Self::deposit_event(Event::ReturnValue{
+                name: "stack pointer",
+                value: emu.sp.encode()
             });
             Ok(())
         }
 
         pub fn get_stack_value(origin:OriginFor<T>,index: u8) -> DispatchResult {
             let emu = emulator::<T>::get();
-            self::deposit_event(Event::ReturnValue{
-                name: Box::leak(Box::new("stack value")),
-                value: Box::new(emu.stack[index])
+            Self::deposit_event(Event::ReturnValue{
+                name: "stack value",
+                value: emu.stack[index as usize].encode()
             });
             Ok(())
         }
 
         pub fn set_vregister(origin:OriginFor<T>, index: u8, value: u8) -> DispatchResult {
             emulator::<T>::mutate(|emu|{
-                emu.virtual_register[index] = value;
+                emu.virtual_registers[index as usize] = value;
             });
             Ok(())
         }
@@ -367,7 +754,7 @@ pub mod pallet {
 
         pub fn set_ram_value_at(origin:OriginFor<T>,index: u8, value: u8) -> DispatchResult {
             emulator::<T>::mutate(|emu|{
-                emu.ram[index] = value;
+                emu.ram[index as usize] = value;
             });
             Ok(())
         }
@@ -388,7 +775,7 @@ pub mod pallet {
 
         pub fn set_stack_value(origin:OriginFor<T>,index: u8, value: u16) -> DispatchResult {
             emulator::<T>::mutate(|emu|{
-                emu.stack[index] = value;
+                emu.stack[index as usize] = value;
             });
             Ok(())
         }
@@ -400,15 +787,15 @@ pub mod pallet {
             Ok(())
         }
 
-        pub fn set_screen_pixel(origin:OriginFor<T>,index: u8, value: bool) -> DispatchResult {
-            let byte_index = index >> 8;      // Get byte position
-            let bit_position = index & 255;    // Get bit position
+        pub fn set_screen_pixel(origin:OriginFor<T>,index: u16, value: bool) -> DispatchResult {
+            let byte_index = (index >> 3) as usize; // Get byte position
+            let bit_position = 7 - (index & 7) as u8; // Get bit position within the byte (MSB = leftmost pixel)
 
             emulator::<T>::mutate(|emu|{
                 if value {
-                    self.screen[byte_index] |= 1 << bit_position;
+                    emu.display[byte_index] |= 1u8 << bit_position;
                 } else {
-                    self.screen[byte_index] &= !(1 << bit_position);
+                    emu.display[byte_index] &= !(1u8 << bit_position);
                 }
             });
             Ok(())
@@ -417,13 +804,13 @@ pub mod pallet {
         pub fn is_display_cleared(origin:OriginFor<T>,) -> DispatchResult {
             let emu = emulator::<T>::get();
             let is_cleared = {
-                emu.screen[0] == 0 && emu.screen[1] == 0 && emu.screen[2] == 0 && emu.screen[3] == 0 && emu.screen[4] == 0
-                    && emu.screen[5] == 0 && emu.screen[6] == 0 && emu.screen[7] == 0
+                emu.display[0] == 0 && emu.display[1] == 0 && emu.display[2] == 0 && emu.display[3] == 0 && emu.display[4] == 0
+                    && emu.display[5] == 0 && emu.display[6] == 0 && emu.display[7] == 0
             };
-            self::deposit_event(
+            Self::deposit_event(
                 Event::ReturnValue {
-                    name: Box::leak(Box::new("display_cleared")),
-                    value: Box::new(is_cleared)
+                    name: "display_cleared",
+                    value: is_cleared.encode()
                 }
             );
             Ok(())
@@ -440,7 +827,15 @@ pub mod pallet {
         /// stack underflow
         StackUndeflow,
         /// stack overflow
-        StackOverflow
+        StackOverflow,
+        /// an opcode that does not map to any known CHIP-8 instruction
+        UnknownOpcode,
+        /// the breakpoint set is already at capacity
+        TooManyBreakpoints,
+        /// the snapshot's version header is not understood by this runtime
+        UnsupportedSnapshotVersion,
+        /// the snapshot's pc or program_size fall outside RAM bounds
+        SnapshotOutOfBounds
     }
 
     #[pallet::event]
@@ -448,7 +843,190 @@ pub mod pallet {
     pub enum Event<T: Config> {
         ReturnValue {
             name: &'static str,
-            value: Box<dyn Default>
+            value: Vec<u8>
+        },
+        /// The run loop halted because it reached a breakpoint address.
+        BreakpointHit {
+            pc: u16
+        },
+        /// The run loop halted because the instruction at `pc` could not be
+        /// fetched or executed (out-of-bounds fetch or memory access, unknown
+        /// opcode, or stack overflow/underflow).
+        ExecutionFault {
+            pc: u16
+        },
+        /// The sound timer reached zero; front-ends can drive audio off this.
+        Beep {
+            duration: u8
+        }
+    }
+}
+
+// ---------------------------* conformance harness *--------------------------- //
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pallet::Emulator;
+    use frame::deps::sp_core::H256;
+    use frame::testing_prelude::*;
+
+    type Block = frame_system::mocking::MockBlock<Test>;
+
+    frame_support::construct_runtime!(
+        pub enum Test {
+            System: frame_system,
+            SubChip8: crate::pallet,
+        }
+    );
+
+    #[frame_support::derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+    impl frame_system::Config for Test {
+        type Block = Block;
+    }
+
+    /// Fixed randomness for the test runtime: determinism is exactly what the
+    /// `CXNN` source promises, so a constant seed keeps assertions reproducible.
+    pub struct DummyRandomness;
+    impl frame_support::traits::Randomness<H256, BlockNumberFor<Test>> for DummyRandomness {
+        fn random(_subject: &[u8]) -> (H256, BlockNumberFor<Test>) {
+            (H256::default(), 0)
+        }
+    }
+
+    impl crate::pallet::Config for Test {
+        type RuntimeEvent = RuntimeEvent;
+        type Randomness = DummyRandomness;
+    }
+
+    /// Build externalities with the fontset loaded, mirroring genesis.
+    fn new_test_ext() -> TestState {
+        let mut ext = TestState::new_empty();
+        ext.execute_with(|| {
+            crate::pallet::emulator::<Test>::mutate(|emu| {
+                emu.pc = START_ADDR;
+                emu.ram[0..80].copy_from_slice(&FONTSET);
+            });
+        });
+        ext
+    }
+
+    /// Load `rom` at the program start address and run up to `max_ticks`
+    /// instructions, stopping early once the run loop halts. Returns the final
+    /// machine so tests can assert on display, registers and flags.
+    fn run_until_halt(rom: &[u8], max_ticks: u32) -> Emulator {
+        crate::pallet::emulator::<Test>::mutate(|emu| {
+            emu.pc = START_ADDR;
+            emu.program_size = U256::from(rom.len());
+            emu.ram[START_ADDR as usize..START_ADDR as usize + rom.len()]
+                .copy_from_slice(rom);
+        });
+        for _ in 0..max_ticks {
+            if crate::pallet::Halted::<Test>::get() {
+                break;
+            }
+            SubChip8::tick();
         }
+        crate::pallet::emulator::<Test>::get()
+    }
+
+    #[test]
+    fn set_and_add_immediate() {
+        new_test_ext().execute_with(|| {
+            // 6A05  V A = 0x05
+            // 7A03  V A += 0x03
+            let emu = run_until_halt(&[0x6A, 0x05, 0x7A, 0x03], 2);
+            assert_eq!(emu.virtual_registers[0xA], 0x08);
+        });
+    }
+
+    #[test]
+    fn add_sets_carry_flag() {
+        new_test_ext().execute_with(|| {
+            // 60FF V0 = 0xFF; 6101 V1 = 0x01; 8014 V0 += V1 -> wraps, VF = 1
+            let emu = run_until_halt(&[0x60, 0xFF, 0x61, 0x01, 0x80, 0x14], 3);
+            assert_eq!(emu.virtual_registers[0], 0x00);
+            assert_eq!(emu.virtual_registers[0xF], 1);
+        });
+    }
+
+    #[test]
+    fn sub_clears_borrow_flag() {
+        new_test_ext().execute_with(|| {
+            // 6005 V0 = 5; 6103 V1 = 3; 8015 V0 -= V1 -> 2, VF = 1 (no borrow)
+            let emu = run_until_halt(&[0x60, 0x05, 0x61, 0x03, 0x80, 0x15], 3);
+            assert_eq!(emu.virtual_registers[0], 2);
+            assert_eq!(emu.virtual_registers[0xF], 1);
+        });
+    }
+
+    #[test]
+    fn store_and_load_registers() {
+        new_test_ext().execute_with(|| {
+            // 6001 V0=1; 6102 V1=2; A300 I=0x300; F155 store V0..=V1; F265 load into V0..=V2
+            let emu = run_until_halt(
+                &[0x60, 0x01, 0x61, 0x02, 0xA3, 0x00, 0xF1, 0x55, 0xF2, 0x65],
+                5,
+            );
+            assert_eq!(emu.ram[0x300], 1);
+            assert_eq!(emu.ram[0x301], 2);
+            assert_eq!(emu.virtual_registers[0], 1);
+            assert_eq!(emu.virtual_registers[1], 2);
+        });
+    }
+
+    #[test]
+    fn draw_sprite_sets_pixels() {
+        new_test_ext().execute_with(|| {
+            // A000 I = font '0' (5 bytes at 0); 6000 V0=0; 6100 V1=0; D015 draw 5 rows
+            let emu = run_until_halt(&[0xA0, 0x00, 0x60, 0x00, 0x61, 0x00, 0xD0, 0x15], 4);
+            // No prior pixels were set, so the draw cannot have flipped one off.
+            assert_eq!(emu.virtual_registers[0xF], 0);
+            // Hash the whole frame against the expected bitmap of the '0' glyph
+            // drawn at (0,0): rows land in display[row * 8]. A regression anywhere
+            // in DXYN changes the frame and therefore the hash.
+            let mut expected = [0u8; 256];
+            expected[0] = 0xF0;
+            expected[8] = 0x90;
+            expected[16] = 0x90;
+            expected[24] = 0x90;
+            expected[32] = 0xF0;
+            assert_eq!(
+                sp_io::hashing::blake2_256(&emu.display),
+                sp_io::hashing::blake2_256(&expected)
+            );
+        });
+    }
+
+    #[test]
+    fn call_and_return_balance_the_stack() {
+        new_test_ext().execute_with(|| {
+            // 0x200 2206 call 0x206
+            // 0x202 60AA V0 = 0xAA   (runs after the subroutine returns)
+            // 0x204 0000 no-op
+            // 0x206 61BB V1 = 0xBB   (subroutine body)
+            // 0x208 00EE return
+            let emu = run_until_halt(
+                &[0x22, 0x06, 0x60, 0xAA, 0x00, 0x00, 0x61, 0xBB, 0x00, 0xEE],
+                4,
+            );
+            assert_eq!(emu.virtual_registers[0], 0xAA);
+            assert_eq!(emu.virtual_registers[1], 0xBB);
+            // The call pushed and the return popped, leaving the pointer balanced.
+            assert_eq!(emu.sp, 0);
+        });
+    }
+
+    #[test]
+    fn random_is_masked_and_counter_advances() {
+        new_test_ext().execute_with(|| {
+            // C000 V0 = rand & 0x00; C10F V1 = rand & 0x0F
+            let emu = run_until_halt(&[0xC0, 0x00, 0xC1, 0x0F], 2);
+            // Masking with 0x00 must zero the register regardless of the draw.
+            assert_eq!(emu.virtual_registers[0], 0);
+            // Masking with 0x0F can only leave the low nibble set.
+            assert_eq!(emu.virtual_registers[1] & 0xF0, 0);
+            // Each CXNN advances the per-instruction counter so repeats differ.
+            assert_eq!(emu.instruction_counter, 2);
+        });
     }
 }